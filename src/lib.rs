@@ -1,5 +1,14 @@
 use core::fmt;
-use std::{io, process::Command, string::FromUtf8Error};
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    io::{self, BufRead, BufReader, Read},
+    path::PathBuf,
+    process::{Command, Stdio},
+    string::FromUtf8Error,
+    thread,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug)]
 pub enum Errors {
@@ -7,6 +16,22 @@ pub enum Errors {
     IO(io::Error),
     Custom(String),
     STDERR(String),
+    /// A command ran to completion but exited with a non-zero (or signalled)
+    /// status. Carries the full command string plus both captured streams so
+    /// the error is self-describing without a caller needing to re-run it.
+    ExitedNonZero {
+        command: String,
+        status: String,
+        stdout: String,
+        stderr: String,
+    },
+    /// A [`Runner`] sequence stopped because one of its commands failed.
+    /// Carries the full [`CmdOut`] so the caller can see every command that
+    /// was attempted, not just the one that broke.
+    Pipeline(CmdOut),
+    /// A [`CommandBuilder`] with a `.timeout()` was killed because it ran
+    /// longer than allowed.
+    Timeout { command: String, elapsed: Duration },
 }
 
 impl fmt::Display for Errors {
@@ -16,6 +41,20 @@ impl fmt::Display for Errors {
             Errors::IO(ref err) => err.fmt(f),
             Errors::Custom(ref err) => write!(f, "ERROR: {})", err),
             Errors::STDERR(ref err) => write!(f, "ERROR: {})", err),
+            Errors::ExitedNonZero {
+                ref command,
+                ref status,
+                ref stdout,
+                ref stderr,
+            } => write!(
+                f,
+                "command \"{command}\" failed: {status}\nstdout={stdout}\nstderr={stderr}"
+            ),
+            Errors::Pipeline(ref cmd_out) => write!(f, "{}", cmd_out.pretty()),
+            Errors::Timeout {
+                ref command,
+                ref elapsed,
+            } => write!(f, "command \"{command}\" timed out after {elapsed:?}"),
         }
     }
 }
@@ -40,68 +79,662 @@ impl From<String> for Errors {
 
 impl std::error::Error for Errors {}
 
-pub struct CommandOutput(String, u8);
+fn describe_exit(exit_code: Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exit status {code}"),
+        None => "terminated by signal".to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub struct CommandOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    exit_code: Option<i32>,
+    /// The number of bytes the command actually emitted on stdout, even if
+    /// `stdout` was abbreviated by a [`Runner`] capture limit. Equal to
+    /// `stdout_bytes().len()` unless truncation happened.
+    stdout_total_len: usize,
+    stderr_total_len: usize,
+}
 
 impl CommandOutput {
-    pub fn output(&self) -> &str {
-        &self.0
+    /// The raw stdout bytes, unvalidated. Commands that emit binary data or
+    /// non-UTF-8 text can be inspected without losing anything.
+    pub fn stdout_bytes(&self) -> &[u8] {
+        &self.stdout
+    }
+    pub fn stderr_bytes(&self) -> &[u8] {
+        &self.stderr
+    }
+    /// Stdout decoded as UTF-8, replacing any invalid sequences with U+FFFD.
+    /// Prefer this for logging/display; use [`stdout_bytes`](Self::stdout_bytes)
+    /// or a strict API like [`sh`] when correctness of the bytes matters.
+    pub fn stdout_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stdout)
+    }
+    pub fn stderr_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stderr)
+    }
+    /// The original size of stdout before any abbreviation.
+    pub fn stdout_total_len(&self) -> usize {
+        self.stdout_total_len
+    }
+    pub fn stderr_total_len(&self) -> usize {
+        self.stderr_total_len
+    }
+    /// Whether a [`Runner`] capture limit elided part of stdout.
+    pub fn stdout_truncated(&self) -> bool {
+        self.stdout.len() < self.stdout_total_len
+    }
+    pub fn stderr_truncated(&self) -> bool {
+        self.stderr.len() < self.stderr_total_len
+    }
+    /// The process exit code, or `None` if the command was terminated by a
+    /// signal before it could exit normally.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
     }
-    pub fn exit_code(&self) -> &u8 {
-        &self.1
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Builds a `CommandOutput` from freshly captured streams, with no
+    /// abbreviation applied — `stdout_total_len`/`stderr_total_len` are just
+    /// the lengths of `stdout`/`stderr`. Every capture path (buffered,
+    /// streamed, or timeout-bounded) goes through this so they can't drift
+    /// out of sync with each other.
+    fn from_raw(stdout: Vec<u8>, stderr: Vec<u8>, exit_code: Option<i32>) -> CommandOutput {
+        let stdout_total_len = stdout.len();
+        let stderr_total_len = stderr.len();
+        CommandOutput {
+            stdout,
+            stderr,
+            exit_code,
+            stdout_total_len,
+            stderr_total_len,
+        }
+    }
+}
+
+/// Runs an already-configured `Command` and collects its output into a
+/// [`CommandOutput`]. Shared by every entry point so the shell-based and
+/// argv-based APIs agree on how output is captured and errors are mapped.
+fn run(mut command: Command) -> Result<CommandOutput, Errors> {
+    let output = command.output()?;
+    Ok(CommandOutput::from_raw(
+        output.stdout,
+        output.stderr,
+        output.status.code(),
+    ))
+}
+
+/// The interpreter used to run a shell command line. Pick one explicitly
+/// with [`execute_command_with`] when the default for the host platform
+/// isn't what's needed (e.g. forcing `bash` for an array/process-substitution
+/// script on a system whose `sh` is dash).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shell {
+    Sh,
+    Bash,
+    Cmd,
+    Powershell,
+    Custom(PathBuf),
+}
+
+impl Shell {
+    /// `sh` on Unix, `cmd` on Windows — matches what [`execute_command`] and
+    /// [`sh`] use when no shell is specified.
+    pub fn default_for_platform() -> Shell {
+        if cfg!(windows) {
+            Shell::Cmd
+        } else {
+            Shell::Sh
+        }
+    }
+
+    fn program(&self) -> &OsStr {
+        match self {
+            Shell::Sh => OsStr::new("sh"),
+            Shell::Bash => OsStr::new("bash"),
+            Shell::Cmd => OsStr::new("cmd"),
+            Shell::Powershell => OsStr::new("powershell"),
+            Shell::Custom(path) => path.as_os_str(),
+        }
+    }
+
+    /// The flag this shell uses to take a command line as a single argument.
+    fn command_flag(&self) -> &str {
+        match self {
+            Shell::Cmd => "/C",
+            Shell::Powershell => "-Command",
+            Shell::Sh | Shell::Bash | Shell::Custom(_) => "-c",
+        }
     }
 }
 
 pub fn execute_command(command_line: &str) -> Result<CommandOutput, Errors> {
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(format!("{command_line}"))
-        .output();
-    return match output {
-        Ok(output) => {
-            if !output.status.success() {
-                return Ok(CommandOutput(String::from_utf8(output.stderr)?, 1));
-            }
-            Ok(CommandOutput(String::from_utf8(output.stdout)?, 0))
+    execute_command_with(&Shell::default_for_platform(), command_line)
+}
+
+/// As [`execute_command`], but runs the command line through `shell`
+/// instead of the platform default.
+pub fn execute_command_with(shell: &Shell, command_line: &str) -> Result<CommandOutput, Errors> {
+    let mut command = Command::new(shell.program());
+    command.arg(shell.command_flag()).arg(command_line);
+    run(command)
+}
+
+/// Runs `program` directly with `args`, with no shell involved. Prefer this
+/// over [`execute_command`]/[`sh`] whenever the command line is built from
+/// untrusted or unpredictable input (paths, filenames, user data) so that
+/// spaces and shell metacharacters can never be reinterpreted.
+pub fn execute(program: &str, args: &[&str]) -> Result<CommandOutput, Errors> {
+    let mut command = Command::new(program);
+    command.args(args);
+    run(command)
+}
+
+/// As [`execute`], but takes already-built [`OsString`] arguments. This is
+/// what the [`cmd!`] macro expands to, and is the right entry point when an
+/// argument isn't valid UTF-8 (e.g. a path from the filesystem).
+pub fn execute_args(program: &str, args: &[OsString]) -> Result<CommandOutput, Errors> {
+    let mut command = Command::new(program);
+    command.args(args);
+    run(command)
+}
+
+/// Builds a shell-free command invocation: `cmd!("mv", src, dst)` runs `mv`
+/// directly with `src` and `dst` as argv entries, with no `sh -c` in
+/// between. Each argument is converted via `OsString::from`, so `&str`,
+/// `String`, and `PathBuf` can all be mixed in.
+#[macro_export]
+macro_rules! cmd {
+    ($program:expr $(, $arg:expr)* $(,)?) => {{
+        let args: Vec<::std::ffi::OsString> = vec![$(::std::ffi::OsString::from($arg)),*];
+        $crate::execute_args($program, &args)
+    }};
+}
+
+/// Runs `command_line` through the default shell, invoking `on_stdout`/
+/// `on_stderr` with each line of output as it arrives instead of buffering
+/// everything until the command exits. Useful for long-running commands
+/// (builds, installers) whose progress a caller wants to surface live.
+///
+/// Each callback is given a line with its trailing newline stripped,
+/// lossily decoded. The two streams are read on separate threads so that a
+/// flood on one pipe can't block the child from making progress on the
+/// other. The full output is still accumulated and returned as a
+/// [`CommandOutput`] once both readers reach EOF and the child exits.
+pub fn execute_streaming(
+    command_line: &str,
+    mut on_stdout: impl FnMut(&str) + Send,
+    mut on_stderr: impl FnMut(&str) + Send,
+) -> Result<CommandOutput, Errors> {
+    let shell = Shell::default_for_platform();
+    let mut child = Command::new(shell.program())
+        .arg(shell.command_flag())
+        .arg(command_line)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("child spawned with piped stdout");
+    let stderr = child.stderr.take().expect("child spawned with piped stderr");
+
+    let (stdout_buf, stderr_buf) = thread::scope(|scope| {
+        let stdout_handle = scope.spawn(|| read_streamed_lines(stdout, &mut on_stdout));
+        let stderr_handle = scope.spawn(|| read_streamed_lines(stderr, &mut on_stderr));
+        let stdout_buf = stdout_handle.join().expect("stdout reader thread panicked");
+        let stderr_buf = stderr_handle.join().expect("stderr reader thread panicked");
+        (stdout_buf, stderr_buf)
+    });
+
+    let status = child.wait()?;
+    Ok(CommandOutput::from_raw(stdout_buf?, stderr_buf?, status.code()))
+}
+
+/// Reads `reader` line by line, calling `on_line` with each decoded line
+/// (newline stripped) while accumulating the raw bytes, newlines included,
+/// for the caller to fold into the final captured output.
+fn read_streamed_lines(reader: impl Read, on_line: &mut impl FnMut(&str)) -> Result<Vec<u8>, Errors> {
+    let mut reader = BufReader::new(reader);
+    let mut accumulated = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
         }
-        Err(err) => Err(Errors::IO(err)),
-    };
+        accumulated.extend_from_slice(&line);
+        let text = String::from_utf8_lossy(&line);
+        on_line(text.trim_end_matches(['\n', '\r']));
+    }
+    Ok(accumulated)
 }
 
+/// Runs `command_line` through the default shell and returns stdout as a
+/// `String`. Strict: if stdout isn't valid UTF-8 this fails with
+/// `Errors::FromUtf8`, whose underlying `FromUtf8Error` still carries the
+/// original bytes (via `.into_bytes()`) so nothing is silently discarded.
+/// Use [`sh_lossy`] when any binary/non-UTF-8 output should just be
+/// replaced with U+FFFD instead of erroring.
 pub fn sh(command_line: &str) -> Result<String, Errors> {
-    let output = execute_command(command_line);
-    return match output {
-        Ok(output) => {
-            let content = output.output().to_string();
-            if output.1 == 1 {
-                return Err(Errors::STDERR(content))
-            }
-            Ok(content)
-        }
-        Err(err) => Err(err),
-    };
+    sh_with(&Shell::default_for_platform(), command_line)
+}
+
+/// As [`sh`], but runs the command line through `shell` instead of the
+/// platform default.
+pub fn sh_with(shell: &Shell, command_line: &str) -> Result<String, Errors> {
+    let output = execute_command_with(shell, command_line)?;
+    if !output.success() {
+        return Err(Errors::ExitedNonZero {
+            command: command_line.to_string(),
+            status: describe_exit(output.exit_code()),
+            stdout: output.stdout_lossy().into_owned(),
+            stderr: output.stderr_lossy().into_owned(),
+        });
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// As [`sh`], but never fails on invalid UTF-8.
+pub fn sh_lossy(command_line: &str) -> Result<String, Errors> {
+    sh_lossy_with(&Shell::default_for_platform(), command_line)
+}
+
+/// As [`sh_lossy`], but runs the command line through `shell` instead of
+/// the platform default.
+pub fn sh_lossy_with(shell: &Shell, command_line: &str) -> Result<String, Errors> {
+    let output = execute_command_with(shell, command_line)?;
+    if !output.success() {
+        return Err(Errors::ExitedNonZero {
+            command: command_line.to_string(),
+            status: describe_exit(output.exit_code()),
+            stdout: output.stdout_lossy().into_owned(),
+            stderr: output.stderr_lossy().into_owned(),
+        });
+    }
+    Ok(output.stdout_lossy().into_owned())
 }
 
 pub fn execute_command_silent(command_line: &str, log_stderr: bool) -> bool {
-    let output = execute_command(command_line);
-    return match output {
+    match execute_command(command_line) {
         Ok(output) => {
-            if output.exit_code() > &0 {
+            if !output.success() {
                 if log_stderr {
-                    eprintln!("{}", output.output());
+                    eprintln!("{}", output.stderr_lossy());
                 }
                 return false;
             }
             true
         }
         Err(err) => {
-            eprintln!("{}", err.to_string());
+            eprintln!("{}", err);
             false
         }
-    };
+    }
 }
 
 pub fn command_exists(command: &str) -> bool {
-    execute_command_silent(&format!("command -v {command}"), false)
+    if cfg!(windows) {
+        // `command -v` is a POSIX shell builtin; Windows' `cmd` has no
+        // equivalent, so fall back to `where`.
+        execute_command_silent(&format!("where {command}"), false)
+    } else {
+        execute_command_silent(&format!("command -v {command}"), false)
+    }
+}
+
+/// A single command as run by a [`Runner`]: the command line it was given
+/// and the output that command produced.
+#[derive(Debug)]
+pub struct AttemptedCommand {
+    command: String,
+    output: CommandOutput,
+}
+
+impl AttemptedCommand {
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+    pub fn output(&self) -> &CommandOutput {
+        &self.output
+    }
+    pub fn success(&self) -> bool {
+        self.output.success()
+    }
+}
+
+/// The record of a [`Runner`] run: every command that was attempted, in
+/// order, along with its output.
+#[derive(Debug)]
+pub struct CmdOut {
+    attempted: Vec<AttemptedCommand>,
+}
+
+impl CmdOut {
+    pub fn attempted(&self) -> &[AttemptedCommand] {
+        &self.attempted
+    }
+    pub fn success(&self) -> bool {
+        self.attempted.iter().all(AttemptedCommand::success)
+    }
+
+    /// Formats the run as `$ cmd ✓` / `$ cmd ✗ (exit N)` lines, one per
+    /// attempted command, with the failing command's stderr indented
+    /// beneath it so the broken step is obvious at a glance.
+    pub fn pretty(&self) -> String {
+        let mut report = String::new();
+        for attempt in &self.attempted {
+            if attempt.success() {
+                report.push_str(&format!("$ {} \u{2713}\n", attempt.command));
+            } else {
+                let status = match attempt.output.exit_code() {
+                    Some(code) => format!("exit {code}"),
+                    None => "terminated by signal".to_string(),
+                };
+                report.push_str(&format!("$ {} \u{2717} ({status})\n", attempt.command));
+                for line in attempt.output.stderr_lossy().lines() {
+                    report.push_str(&format!("    {line}\n"));
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Runs a sequence of shell command lines, recording every attempt. By
+/// default it stops at the first failure (`set -e` style); disable that
+/// with [`Runner::stop_on_failure`] to run the whole sequence regardless.
+pub struct Runner {
+    stop_on_failure: bool,
+    capture_limit: Option<usize>,
+}
+
+impl Runner {
+    pub fn new() -> Runner {
+        Runner {
+            stop_on_failure: true,
+            capture_limit: None,
+        }
+    }
+
+    pub fn stop_on_failure(mut self, stop_on_failure: bool) -> Runner {
+        self.stop_on_failure = stop_on_failure;
+        self
+    }
+
+    /// Caps each command's captured stdout/stderr at `limit` bytes: once a
+    /// stream exceeds it, only the first and last `limit / 2` bytes are
+    /// kept, with a `<<< N bytes omitted >>>` marker spliced in between at a
+    /// UTF-8 character boundary. The original size is still available via
+    /// [`CommandOutput::stdout_total_len`]/[`stderr_total_len`](CommandOutput::stderr_total_len).
+    /// Useful for commands that can produce megabytes of output that would
+    /// otherwise be wasteful to buffer and unreadable to print.
+    pub fn capture_limit(mut self, limit: usize) -> Runner {
+        self.capture_limit = Some(limit);
+        self
+    }
+
+    /// Runs each command line in turn. Returns `Ok(CmdOut)` if every
+    /// attempted command succeeded, or `Err(Errors::Pipeline(CmdOut))`
+    /// otherwise — either way the `CmdOut` records every command that ran.
+    /// If the shell itself cannot be spawned, that `io::Error` is returned
+    /// as `Errors::IO` and no `CmdOut` is produced.
+    pub fn run(&self, command_lines: &[&str]) -> Result<CmdOut, Errors> {
+        let mut attempted = Vec::new();
+        for &command_line in command_lines {
+            let mut output = execute_command(command_line)?;
+            if let Some(limit) = self.capture_limit {
+                output = abbreviate_output(output, limit);
+            }
+            let succeeded = output.success();
+            attempted.push(AttemptedCommand {
+                command: command_line.to_string(),
+                output,
+            });
+            if !succeeded && self.stop_on_failure {
+                break;
+            }
+        }
+        let cmd_out = CmdOut { attempted };
+        if cmd_out.success() {
+            Ok(cmd_out)
+        } else {
+            Err(Errors::Pipeline(cmd_out))
+        }
+    }
+}
+
+/// Replaces `output`'s streams with abbreviated versions if they exceed
+/// `limit` bytes, preserving the original lengths.
+fn abbreviate_output(output: CommandOutput, limit: usize) -> CommandOutput {
+    let (stdout, stdout_total_len) = abbreviate(output.stdout, limit);
+    let (stderr, stderr_total_len) = abbreviate(output.stderr, limit);
+    CommandOutput {
+        stdout,
+        stderr,
+        exit_code: output.exit_code,
+        stdout_total_len,
+        stderr_total_len,
+    }
+}
+
+/// Keeps the first and last `limit / 2` bytes of `bytes`, splicing a
+/// `<<< N bytes omitted >>>` marker in between, if `bytes` is longer than
+/// `limit`. Splits are moved to the nearest UTF-8 character boundary so the
+/// result is always valid to decode lossily. Mirrors the `read2_abbreviated`
+/// approach used by compiletest for capturing huge test output.
+fn abbreviate(bytes: Vec<u8>, limit: usize) -> (Vec<u8>, usize) {
+    let total = bytes.len();
+    if total <= limit {
+        return (bytes, total);
+    }
+    let half = limit / 2;
+    let head_end = floor_char_boundary(&bytes, half);
+    let tail_start = ceil_char_boundary(&bytes, total - half);
+    let elided = tail_start - head_end;
+
+    let mut out = Vec::with_capacity(head_end + (total - tail_start) + 32);
+    out.extend_from_slice(&bytes[..head_end]);
+    out.extend_from_slice(format!("\n<<< {elided} bytes omitted >>>\n").as_bytes());
+    out.extend_from_slice(&bytes[tail_start..]);
+    (out, total)
+}
+
+fn floor_char_boundary(bytes: &[u8], index: usize) -> usize {
+    let mut i = index.min(bytes.len());
+    while i > 0 && (bytes[i] & 0b1100_0000) == 0b1000_0000 {
+        i -= 1;
+    }
+    i
+}
+
+fn ceil_char_boundary(bytes: &[u8], index: usize) -> usize {
+    let mut i = index.min(bytes.len());
+    while i < bytes.len() && (bytes[i] & 0b1100_0000) == 0b1000_0000 {
+        i += 1;
+    }
+    i
+}
+
+impl Default for Runner {
+    fn default() -> Runner {
+        Runner::new()
+    }
+}
+
+/// Builds a single command invocation with a working directory, extra
+/// environment variables, and/or a timeout — the per-command controls the
+/// plain `execute_command`/`sh` functions don't expose.
+pub struct CommandBuilder {
+    shell: Shell,
+    command_line: String,
+    current_dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    timeout: Option<Duration>,
+}
+
+impl CommandBuilder {
+    pub fn new(command_line: &str) -> CommandBuilder {
+        CommandBuilder {
+            shell: Shell::default_for_platform(),
+            command_line: command_line.to_string(),
+            current_dir: None,
+            envs: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    pub fn shell(mut self, shell: Shell) -> CommandBuilder {
+        self.shell = shell;
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> CommandBuilder {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> CommandBuilder {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn envs<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> CommandBuilder
+    where
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        self.envs
+            .extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Bounds how long the command may run. If it's still running once
+    /// `timeout` elapses, the child is killed and `run()` returns
+    /// `Errors::Timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> CommandBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn run(self) -> Result<CommandOutput, Errors> {
+        let mut command = Command::new(self.shell.program());
+        command.arg(self.shell.command_flag()).arg(&self.command_line);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        command.envs(self.envs);
+
+        match self.timeout {
+            Some(timeout) => run_with_timeout(command, &self.command_line, timeout),
+            None => run(command),
+        }
+    }
+}
+
+/// Runs `command`, killing it and returning `Errors::Timeout` if it's still
+/// running after `timeout`. Stdout/stderr are drained on background threads
+/// so a chatty command can't deadlock against the timeout poll loop.
+fn run_with_timeout(
+    mut command: Command,
+    command_line: &str,
+    timeout: Duration,
+) -> Result<CommandOutput, Errors> {
+    // Putting the child in its own process group (below) detaches it from
+    // the terminal's job control, so a child that tries to read stdin would
+    // get SIGTTIN'd and stall silently instead of running — and since the
+    // point of this path is to bound execution time, leave stdin closed
+    // rather than let the child block on a controlling tty it no longer has.
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    spawn_in_own_process_group(&mut command);
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take().expect("child spawned with piped stdout");
+    let stderr = child.stderr.take().expect("child spawned with piped stderr");
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        let stdout_handle = scope.spawn(|| read_to_end(stdout));
+        let stderr_handle = scope.spawn(|| read_to_end(stderr));
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                let stdout = stdout_handle.join().expect("stdout reader thread panicked")?;
+                let stderr = stderr_handle.join().expect("stderr reader thread panicked")?;
+                return Ok(CommandOutput::from_raw(stdout, stderr, status.code()));
+            }
+            if start.elapsed() >= timeout {
+                kill_process_tree(&mut child);
+                let _ = child.wait();
+                let _ = stdout_handle.join();
+                let _ = stderr_handle.join();
+                return Err(Errors::Timeout {
+                    command: command_line.to_string(),
+                    elapsed: start.elapsed(),
+                });
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    })
+}
+
+/// Puts `command`'s future child in its own process group (Unix only) so
+/// that the whole tree it spawns can be killed as a unit. This matters
+/// because `CommandBuilder` runs through a shell (by default `sh -c
+/// "<command_line>"` on Unix), and on systems where `/bin/sh` is dash (most
+/// Debian/Ubuntu installs), dash *forks* a grandchild to run the real
+/// command rather than exec-replacing itself the way bash does in tail
+/// position — so killing just the `sh` PID leaves the real command running
+/// as an orphan.
+fn spawn_in_own_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = command;
+    }
+}
+
+/// Kills `child` along with the rest of its process group/tree, not just
+/// the immediate `sh`/`cmd` PID. On Unix this signals the whole process
+/// group set up by [`spawn_in_own_process_group`]; on Windows it shells out
+/// to `taskkill /T`, since the standard library has no equivalent of a job
+/// object here.
+fn kill_process_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        extern "C" {
+            fn kill(pid: i32, sig: i32) -> i32;
+        }
+        const SIGKILL: i32 = 9;
+        // A negative pid targets the process group rather than just the
+        // single process `Command` spawned.
+        unsafe {
+            kill(-(child.id() as i32), SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .output();
+    }
+    let _ = child.kill();
+}
+
+fn read_to_end(mut reader: impl Read) -> Result<Vec<u8>, Errors> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    Ok(buf)
 }
 
 #[cfg(test)]
@@ -133,6 +766,13 @@ mod tests {
         assert_eq!(result, false);
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_execute_command_with_explicit_shell() {
+        let output = execute_command_with(&Shell::Bash, "echo $BASH_VERSION").unwrap();
+        assert!(!output.stdout_lossy().trim().is_empty());
+    }
+
     #[test]
     fn test_command_with_errors() {
         let result = sh("mv file-does-not-exist.txt /location/does/not/exist");
@@ -144,4 +784,152 @@ mod tests {
         let result = sh("echo hello");
         assert_eq!(result.is_err(), false);
     }
+
+    #[test]
+    fn test_exited_non_zero_carries_streams() {
+        let err = sh("echo oops 1>&2; exit 3").unwrap_err();
+        match err {
+            Errors::ExitedNonZero {
+                ref status,
+                ref stderr,
+                ..
+            } => {
+                assert!(status.contains('3'));
+                assert!(stderr.contains("oops"));
+            }
+            _ => panic!("expected ExitedNonZero, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_execute_runs_argv_with_no_shell() {
+        let output = execute("echo", &["hello world"]).unwrap();
+        assert_eq!(output.stdout_lossy(), "hello world\n");
+    }
+
+    #[test]
+    fn test_cmd_macro_builds_argv() {
+        let output = cmd!("echo", "a", "b").unwrap();
+        assert_eq!(output.stdout_lossy(), "a b\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sh_lossy_replaces_invalid_utf8() {
+        // `\xHH` printf escapes are a bash/zsh extension, not POSIX, so this
+        // is pinned to `Shell::Bash` rather than run through `sh_lossy`'s
+        // default shell (which may be dash, where these bytes would never
+        // get produced in the first place).
+        let result = sh_lossy_with(&Shell::Bash, "printf '\\xff\\xfehello'").unwrap();
+        assert!(result.contains("hello"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sh_strict_surfaces_invalid_bytes() {
+        let err = sh_with(&Shell::Bash, "printf '\\xff\\xfehello'").unwrap_err();
+        match err {
+            Errors::FromUtf8(inner) => assert_eq!(&inner.into_bytes()[2..], b"hello"),
+            other => panic!("expected FromUtf8, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_execute_streaming_invokes_callback_per_line() {
+        let mut stdout_lines = Vec::new();
+        let mut stderr_lines = Vec::new();
+        let output = execute_streaming(
+            "echo one; echo two; echo err 1>&2",
+            |line| stdout_lines.push(line.to_string()),
+            |line| stderr_lines.push(line.to_string()),
+        )
+        .unwrap();
+        assert_eq!(stdout_lines, vec!["one", "two"]);
+        assert_eq!(stderr_lines, vec!["err"]);
+        assert_eq!(output.stdout_lossy(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_runner_stops_on_first_failure_by_default() {
+        let err = Runner::new()
+            .run(&["echo first", "exit 7", "echo never"])
+            .unwrap_err();
+        match err {
+            Errors::Pipeline(cmd_out) => {
+                assert_eq!(cmd_out.attempted().len(), 2);
+                assert!(!cmd_out.success());
+                assert!(cmd_out.pretty().contains("(exit 7)"));
+            }
+            other => panic!("expected Pipeline, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_runner_continues_past_failure_when_configured() {
+        let err = Runner::new()
+            .stop_on_failure(false)
+            .run(&["echo first", "exit 7", "echo third"])
+            .unwrap_err();
+        match err {
+            Errors::Pipeline(cmd_out) => assert_eq!(cmd_out.attempted().len(), 3),
+            other => panic!("expected Pipeline, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_runner_abbreviates_output_over_capture_limit() {
+        let cmd_out = Runner::new()
+            .capture_limit(20)
+            .run(&["printf '%080d' 0"])
+            .unwrap();
+        let output = cmd_out.attempted()[0].output();
+        assert_eq!(output.stdout_total_len(), 80);
+        assert!(output.stdout_truncated());
+        assert!(output.stdout_lossy().contains("bytes omitted"));
+        assert!(output.stdout_bytes().len() < 80);
+    }
+
+    #[test]
+    fn test_abbreviate_is_noop_under_limit() {
+        let (bytes, total) = abbreviate(b"short".to_vec(), 100);
+        assert_eq!(bytes, b"short");
+        assert_eq!(total, 5);
+    }
+
+    #[test]
+    fn test_command_builder_sets_cwd_and_env() {
+        let output = CommandBuilder::new("pwd; echo \"$FOO\"")
+            .current_dir("/tmp")
+            .env("FOO", "bar")
+            .run()
+            .unwrap();
+        let stdout = output.stdout_lossy();
+        let mut lines = stdout.lines();
+        assert_eq!(lines.next(), Some("/tmp"));
+        assert_eq!(lines.next(), Some("bar"));
+    }
+
+    #[test]
+    fn test_command_builder_times_out() {
+        let err = CommandBuilder::new("sleep 5")
+            .timeout(Duration::from_millis(50))
+            .run()
+            .unwrap_err();
+        match err {
+            Errors::Timeout { command, elapsed } => {
+                assert_eq!(command, "sleep 5");
+                assert!(elapsed < Duration::from_secs(5));
+            }
+            other => panic!("expected Timeout, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_command_builder_within_timeout_succeeds() {
+        let output = CommandBuilder::new("echo hello")
+            .timeout(Duration::from_secs(5))
+            .run()
+            .unwrap();
+        assert_eq!(output.stdout_lossy(), "hello\n");
+    }
 }